@@ -0,0 +1,207 @@
+//! Locality-sensitive index for near-duplicate search over many [Digest]s.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::Digest;
+
+/// Default number of bits sampled per hash table.
+const DEFAULT_K: usize = 8;
+
+/// Default number of hash tables (bands).
+const DEFAULT_L: usize = 16;
+
+/// A locality-sensitive index over [Digest]s that answers "which of the inserted digests are at least `threshold`
+/// similar to this one" in sub-linear time, instead of requiring an `O(n)` scan with [compare](crate::compare).
+///
+/// The index uses bit-sampling LSH banding: it builds `L` hash tables, each defined by `k` randomly chosen bit
+/// positions out of the 256-bit digest. A digest's key in table `i` is the concatenation of its bits at that table's
+/// `k` positions, and the digest is inserted into all `L` tables. A query computes the same `L` signatures, unions the
+/// candidate ids found in the colliding buckets, then verifies each candidate against the query with the exact
+/// Hamming [compare](Digest::compare) and keeps those meeting the threshold.
+///
+/// For two digests with true similarity `s` (as a fraction of matching bits), the probability that they collide in at
+/// least one of the `L` tables is `1 - (1 - s^k)^L`. Larger `k` makes each table more selective (fewer false
+/// positives, more false negatives); larger `L` makes a collision more likely overall (fewer false negatives, more
+/// candidates to verify).
+#[derive(Debug, Clone)]
+pub struct NilsimsaIndex {
+    k: usize,
+    bit_positions: Vec<Vec<usize>>,
+    tables: Vec<HashMap<u64, Vec<usize>>>,
+    digests: Vec<Digest>,
+}
+
+impl NilsimsaIndex {
+    /// Returns a new index using the default `k` and `L` tunables, seeded for reproducible bit selection.
+    pub fn new() -> Self {
+        Self::with_params(DEFAULT_K, DEFAULT_L, 0x9e3779b97f4a7c15)
+    }
+
+    /// Returns a new index with `k` bit positions per table, `L` tables, and a seed controlling which bit positions
+    /// are sampled. The same seed always produces the same bit positions, so two indexes built with the same
+    /// parameters and seed are directly comparable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is greater than 64, since a table key must fit into a `u64` signature.
+    pub fn with_params(k: usize, l: usize, seed: u64) -> Self {
+        assert!(k <= 64, "k must not exceed 64 bits");
+
+        let mut rng = SplitMix64::new(seed);
+        let bit_positions = (0..l)
+            .map(|_| (0..k).map(|_| (rng.next() % 256) as usize).collect())
+            .collect();
+
+        Self {
+            k,
+            bit_positions,
+            tables: vec![HashMap::new(); l],
+            digests: Vec::new(),
+        }
+    }
+
+    /// Inserts a digest into the index and returns the id it was assigned, which is stable for the lifetime of the
+    /// index and can be used to look up the original digest with [get](NilsimsaIndex::get).
+    pub fn insert(&mut self, digest: Digest) -> usize {
+        let id = self.digests.len();
+
+        for (table, positions) in self.tables.iter_mut().zip(&self.bit_positions) {
+            table.entry(signature(&digest, positions)).or_default().push(id);
+        }
+
+        self.digests.push(digest);
+        id
+    }
+
+    /// Returns the ids of all inserted digests with similarity `>= threshold` to `digest`, as computed by
+    /// [Digest::compare]. Ids are returned in ascending order.
+    pub fn query(&self, digest: &Digest, threshold: u8) -> Vec<usize> {
+        let mut candidates = HashSet::new();
+
+        for (table, positions) in self.tables.iter().zip(&self.bit_positions) {
+            if let Some(ids) = table.get(&signature(digest, positions)) {
+                candidates.extend(ids.iter().copied());
+            }
+        }
+
+        let mut matches: Vec<usize> = candidates
+            .into_iter()
+            .filter(|&id| self.digests[id].compare(digest) >= threshold)
+            .collect();
+        matches.sort_unstable();
+        matches
+    }
+
+    /// Returns the digest previously inserted under `id`, if any.
+    pub fn get(&self, id: usize) -> Option<&Digest> {
+        self.digests.get(id)
+    }
+
+    /// Returns the number of digests stored in the index.
+    pub fn len(&self) -> usize {
+        self.digests.len()
+    }
+
+    /// Returns `true` if the index holds no digests.
+    pub fn is_empty(&self) -> bool {
+        self.digests.is_empty()
+    }
+
+    /// Returns the number of bit positions sampled per hash table.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the number of hash tables (bands) the index maintains.
+    pub fn l(&self) -> usize {
+        self.tables.len()
+    }
+}
+
+impl Default for NilsimsaIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn signature(digest: &Digest, positions: &[usize]) -> u64 {
+    let bytes = digest.as_ref();
+    positions.iter().fold(0u64, |acc, &position| {
+        let bit = (bytes[position / 8] >> (position % 8)) & 1;
+        (acc << 1) | bit as u64
+    })
+}
+
+/// A minimal SplitMix64 generator, used only to deterministically pick bit positions from a seed.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest(byte: u8) -> Digest {
+        Digest::from([byte; 32])
+    }
+
+    #[test]
+    fn query_on_empty_index_returns_no_matches() {
+        let index = NilsimsaIndex::new();
+        assert!(index.query(&digest(0x00), 0).is_empty());
+    }
+
+    #[test]
+    fn query_finds_near_duplicate_and_excludes_dissimilar() {
+        // k = 0 means every digest's signature is the empty bit string, so every insertion is a
+        // candidate in every table and the exact Hamming check inside `query` is what actually
+        // does the filtering - this isolates that filtering from the probabilistic LSH collision.
+        let mut index = NilsimsaIndex::with_params(0, 4, 42);
+
+        let near = digest(0x00);
+        let mut far_bytes = [0u8; 32];
+        far_bytes[0] = 0xff;
+        let far = Digest::from(far_bytes);
+
+        let near_id = index.insert(near);
+        let far_id = index.insert(far);
+
+        // near.compare(&near) == 128, near.compare(&far) == 120, so a threshold of 121 keeps the
+        // former and drops the latter.
+        let matches = index.query(&near, 121);
+        assert!(matches.contains(&near_id));
+        assert!(!matches.contains(&far_id));
+    }
+
+    #[test]
+    fn duplicate_insertions_get_distinct_ids_and_are_both_returned() {
+        let mut index = NilsimsaIndex::with_params(0, 4, 42);
+        let d = digest(0x00);
+
+        let first = index.insert(d);
+        let second = index.insert(d);
+        assert_ne!(first, second);
+
+        let matches = index.query(&d, 128);
+        assert!(matches.contains(&first));
+        assert!(matches.contains(&second));
+    }
+
+    #[test]
+    #[should_panic]
+    fn with_params_panics_when_k_exceeds_64() {
+        NilsimsaIndex::with_params(65, 1, 0);
+    }
+}
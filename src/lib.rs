@@ -14,12 +14,21 @@
 //! hasher.update("test string");
 //! let digest = hasher.digest();
 //! assert_eq!(
-//!     digest,
+//!     digest.to_string(),
 //!     "42c82c184080082040001004000000084e1043b0c0925829003e84c860410010"
 //! );
 //! # }
 //! ```
 
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+mod index;
+
+pub use index::NilsimsaIndex;
+
 const TRAN: [u8; 256] = [
     0x02, 0xd6, 0x9e, 0x6f, 0xf9, 0x1d, 0x04, 0xab, 0xd0, 0x22, 0x16, 0x1f, 0xd8, 0x73, 0xa1, 0xac, 0x3b, 0x70, 0x62,
     0x96, 0x1e, 0x6e, 0x8f, 0x39, 0x9d, 0x05, 0x14, 0x4a, 0xa6, 0xbe, 0xae, 0x0e, 0xcf, 0xb9, 0x9c, 0x9a, 0xc7, 0x68,
@@ -37,6 +46,8 @@ const TRAN: [u8; 256] = [
     0xc4, 0x37, 0xc8, 0xd2, 0xf6, 0xdf, 0x58, 0x72, 0x4e,
 ];
 
+/// Byte-wise popcount lookup table, used only by the scalar debug-build cross-check of the [compare] Hamming distance
+/// calculation. The comparison itself goes through [compare_bytes], which sums `u64::count_ones()` over whole words.
 const POPC: [u8; 256] = [
     0x00, 0x01, 0x01, 0x02, 0x01, 0x02, 0x02, 0x03, 0x01, 0x02, 0x02, 0x03, 0x02, 0x03, 0x03, 0x04, 0x01, 0x02, 0x02,
     0x03, 0x02, 0x03, 0x03, 0x04, 0x02, 0x03, 0x03, 0x04, 0x03, 0x04, 0x04, 0x05, 0x01, 0x02, 0x02, 0x03, 0x02, 0x03,
@@ -54,13 +65,112 @@ const POPC: [u8; 256] = [
     0x07, 0x05, 0x06, 0x06, 0x07, 0x06, 0x07, 0x07, 0x08,
 ];
 
+/// A 256-bit Nilsimsa hash digest.
+///
+/// This is a thin wrapper around the raw digest bytes that avoids re-decoding hex on every comparison and lets callers
+/// store or serialise digests compactly. It implements [LowerHex](fmt::LowerHex), [UpperHex](fmt::UpperHex) and
+/// [Display](fmt::Display) for formatting, and [FromStr]/[TryFrom]`<&str>` for parsing a 64-character hex string back
+/// into a digest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Digest([u8; 32]);
+
+impl Digest {
+    /// Compares this digest against another with a Hamming distance calculation, operating directly on the raw bytes
+    /// rather than re-decoding hex. Returns an unsigned 8-bit integer in the range `[0, 128]` representing the
+    /// similarity of the two digests, where 0 is most dissimilar and 128 is most similar, or equal.
+    pub fn compare(&self, other: &Digest) -> u8 {
+        compare_bytes(&self.0, &other.0)
+    }
+}
+
+impl From<[u8; 32]> for Digest {
+    fn from(bytes: [u8; 32]) -> Self {
+        Digest(bytes)
+    }
+}
+
+impl AsRef<[u8]> for Digest {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::LowerHex for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in &self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::UpperHex for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in &self.0 {
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(self, f)
+    }
+}
+
+impl FromStr for Digest {
+    type Err = DigestParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = hex::decode(s).map_err(DigestParseError::InvalidHex)?;
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|bytes: Vec<u8>| DigestParseError::InvalidLength(bytes.len()))?;
+
+        Ok(Digest(bytes))
+    }
+}
+
+impl TryFrom<&str> for Digest {
+    type Error = DigestParseError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Error returned when parsing a [Digest] from a string fails.
+#[derive(Debug)]
+pub enum DigestParseError {
+    /// The input was not valid hexadecimal.
+    InvalidHex(hex::FromHexError),
+    /// The input was valid hexadecimal, but did not decode to exactly 32 bytes.
+    InvalidLength(usize),
+}
+
+impl fmt::Display for DigestParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DigestParseError::InvalidHex(e) => write!(f, "invalid hex digest: {}", e),
+            DigestParseError::InvalidLength(len) => {
+                write!(f, "expected a 32-byte (64 hex character) digest, got {} bytes", len)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DigestParseError {}
+
 /// Utility to calculate Nilsimsa hash digests for arbitrarily long string inputs. See the crate-level documentation for
 /// an example of use.
 #[derive(Debug, Clone)]
 pub struct Nilsimsa {
     num_char: usize,
     acc: Vec<u8>,
-    window: Vec<u8>,
+    window: [u8; 4],
+    window_head: usize,
+    window_len: usize,
 }
 
 impl Default for Nilsimsa {
@@ -68,11 +178,45 @@ impl Default for Nilsimsa {
         Self {
             num_char: 0,
             acc: vec![0; 256],
-            window: Vec::new(),
+            window: [0; 4],
+            window_head: 0,
+            window_len: 0,
+        }
+    }
+}
+
+/// A serialisable snapshot of an in-progress [Nilsimsa] hash.
+///
+/// Capturing a [State] with [to_state](Nilsimsa::to_state) lets a long or interrupted stream be checkpointed to disk
+/// and later resumed with [from_state](Nilsimsa::from_state), without losing the accumulated trigram counts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct State {
+    num_char: usize,
+    acc: Vec<u8>,
+    window: Vec<u8>,
+}
+
+/// Error returned when restoring a [Nilsimsa] from a [State] that is structurally invalid, e.g. a checkpoint that was
+/// truncated or corrupted on disk.
+#[derive(Debug)]
+pub enum StateError {
+    /// `acc` must have exactly 256 entries, one per trigram hash bucket.
+    InvalidAccLength(usize),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::InvalidAccLength(len) => {
+                write!(f, "expected acc to have exactly 256 entries, got {}", len)
+            }
         }
     }
 }
 
+impl std::error::Error for StateError {}
+
 impl Nilsimsa {
     /// Returns a new Nilsimsa hash digest utility.
     pub fn new() -> Self {
@@ -81,37 +225,67 @@ impl Nilsimsa {
 
     /// Updates the digest with a given string.
     pub fn update(&mut self, s: &str) {
-        for c in s.bytes() {
+        self.update_bytes(s.as_bytes());
+    }
+
+    /// Updates the digest with a given byte slice. Nilsimsa is defined over raw bytes, so this is the method to use
+    /// when hashing binary data; [update](Nilsimsa::update) is a thin convenience wrapper around it for string input.
+    pub fn update_bytes(&mut self, bytes: &[u8]) {
+        for c in bytes.iter().copied() {
             self.num_char += 1;
 
-            let window_len = self.window.len();
+            let window_len = self.window_len;
+            let (w0, w1, w2, w3) = (
+                self.window_at(0),
+                self.window_at(1),
+                self.window_at(2),
+                self.window_at(3),
+            );
+
             if window_len > 1 {
-                self.acc[tran_hash(c, self.window[0], self.window[1], 0) as usize] += 1;
+                self.acc[tran_hash(c, w0, w1, 0) as usize] += 1;
             }
 
             if window_len > 2 {
-                self.acc[tran_hash(c, self.window[0], self.window[2], 1) as usize] += 1;
-                self.acc[tran_hash(c, self.window[1], self.window[2], 2) as usize] += 1;
+                self.acc[tran_hash(c, w0, w2, 1) as usize] += 1;
+                self.acc[tran_hash(c, w1, w2, 2) as usize] += 1;
             }
 
             if window_len > 3 {
-                self.acc[tran_hash(c, self.window[0], self.window[3], 3) as usize] += 1;
-                self.acc[tran_hash(c, self.window[1], self.window[3], 4) as usize] += 1;
-                self.acc[tran_hash(c, self.window[2], self.window[3], 5) as usize] += 1;
+                self.acc[tran_hash(c, w0, w3, 3) as usize] += 1;
+                self.acc[tran_hash(c, w1, w3, 4) as usize] += 1;
+                self.acc[tran_hash(c, w2, w3, 5) as usize] += 1;
 
-                self.acc[tran_hash(self.window[3], self.window[0], c, 6) as usize] += 1;
-                self.acc[tran_hash(self.window[3], self.window[2], c, 7) as usize] += 1;
+                self.acc[tran_hash(w3, w0, c, 6) as usize] += 1;
+                self.acc[tran_hash(w3, w2, c, 7) as usize] += 1;
             }
 
-            self.window.insert(0, c);
-            if self.window.len() > 4 {
-                self.window.remove(4);
+            self.window_head = (self.window_head + 1) % 4;
+            self.window[self.window_head] = c;
+            if self.window_len < 4 {
+                self.window_len += 1;
             }
         }
     }
 
-    /// Finalise and consume the digest and return the computed Nilsimsa hash digest as a hex string.
-    pub fn digest(self) -> String {
+    /// Returns the `i`-th most recently seen byte still held in the rolling window (`0` is the most recent), reading
+    /// from the fixed-size ring buffer in O(1) instead of shifting a growable `Vec` on every update.
+    fn window_at(&self, i: usize) -> u8 {
+        self.window[(self.window_head + 4 - i) % 4]
+    }
+
+    /// Finalise and consume the digest and return the computed Nilsimsa hash [Digest].
+    pub fn digest(self) -> Digest {
+        self.compute_digest()
+    }
+
+    /// Computes the Nilsimsa hash [Digest] for the data seen so far without consuming the hasher, so that more data
+    /// can still be fed in afterwards.
+    pub fn peek_digest(&self) -> Digest {
+        self.compute_digest()
+    }
+
+    fn compute_digest(&self) -> Digest {
         let num_trigrams = match self.num_char {
             0..=2 => 0,
             3 => 1,
@@ -129,8 +303,133 @@ impl Nilsimsa {
         }
 
         digest.reverse();
-        hex::encode(digest)
+        Digest(digest)
+    }
+
+    /// Captures the current hasher progress as a resumable [State] snapshot.
+    pub fn to_state(&self) -> State {
+        State {
+            num_char: self.num_char,
+            acc: self.acc.clone(),
+            window: (0..self.window_len).map(|i| self.window_at(i)).collect(),
+        }
+    }
+
+    /// Restores a hasher from a previously captured [State] snapshot, continuing where it left off.
+    ///
+    /// `state` is expected to cross a disk or process boundary, so it's validated rather than trusted: an `acc`
+    /// that isn't exactly 256 entries (e.g. from a truncated or corrupted checkpoint) is rejected instead of
+    /// panicking the next time the hasher is used.
+    pub fn from_state(state: State) -> Result<Self, StateError> {
+        if state.acc.len() != 256 {
+            return Err(StateError::InvalidAccLength(state.acc.len()));
+        }
+
+        let window_len = state.window.len().min(4);
+        let window_head = window_len.saturating_sub(1);
+
+        let mut window = [0u8; 4];
+        for (i, &c) in state.window.iter().take(window_len).enumerate() {
+            window[(window_head + 4 - i) % 4] = c;
+        }
+
+        Ok(Self {
+            num_char: state.num_char,
+            acc: state.acc,
+            window,
+            window_head,
+            window_len,
+        })
+    }
+}
+
+/// Allows a [Nilsimsa] hasher to be fed from any [Read](std::io::Read) via [io::copy](std::io::copy), e.g. to hash a
+/// file or network stream without buffering it into memory first.
+impl io::Write for Nilsimsa {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update_bytes(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Integration with the [digest] crate's hashing traits, allowing [Nilsimsa] to be used as a drop-in hasher in generic
+/// hashing pipelines built around the RustCrypto ecosystem.
+#[cfg(feature = "digest")]
+mod digest_impl {
+    use digest::{FixedOutput, FixedOutputReset, HashMarker, Output, OutputSizeUser, Reset, Update};
+
+    use super::Nilsimsa;
+
+    impl OutputSizeUser for Nilsimsa {
+        type OutputSize = digest::consts::U32;
+    }
+
+    impl Update for Nilsimsa {
+        fn update(&mut self, data: &[u8]) {
+            self.update_bytes(data);
+        }
+    }
+
+    impl FixedOutput for Nilsimsa {
+        fn finalize_into(self, out: &mut Output<Self>) {
+            out.copy_from_slice(self.digest().as_ref());
+        }
+    }
+
+    impl FixedOutputReset for Nilsimsa {
+        fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+            out.copy_from_slice(self.clone().digest().as_ref());
+            *self = Nilsimsa::new();
+        }
+    }
+
+    impl Reset for Nilsimsa {
+        fn reset(&mut self) {
+            *self = Nilsimsa::new();
+        }
+    }
+
+    impl HashMarker for Nilsimsa {}
+}
+
+/// Compares two raw 32-byte digests with a Hamming distance calculation. Returns an unsigned 8-bit integer in the
+/// range `[0, 128]` representing the similarity of the two digests, where 0 is most dissimilar and 128 is most
+/// similar, or equal. Digests differing in more than 128 bits all score 0.
+///
+/// This reinterprets each digest as four `u64` words, XORs them pairwise, and sums `u64::count_ones()` across the
+/// words, avoiding the 32 table indirections through [POPC] that a byte-at-a-time comparison would need.
+pub fn compare_bytes(a: &[u8; 32], b: &[u8; 32]) -> u8 {
+    let mut distance = 0u32;
+
+    for i in 0..4 {
+        let word_a = u64::from_be_bytes(a[i * 8..i * 8 + 8].try_into().unwrap());
+        let word_b = u64::from_be_bytes(b[i * 8..i * 8 + 8].try_into().unwrap());
+        distance += (word_a ^ word_b).count_ones();
     }
+
+    let result = 128u32.saturating_sub(distance) as u8;
+    debug_assert_eq!(
+        result,
+        compare_bytes_scalar(a, b),
+        "word-wise and scalar popcount disagree"
+    );
+    result
+}
+
+/// Scalar byte-at-a-time fallback for [compare_bytes] via the [POPC] lookup table. Only used to cross-check the
+/// word-wise fast path in debug builds; release builds never call it.
+fn compare_bytes_scalar(a: &[u8; 32], b: &[u8; 32]) -> u8 {
+    let mut bits = 0u32;
+
+    for (x, y) in a.iter().zip(b.iter()) {
+        bits += POPC[(x ^ y) as usize] as u32;
+    }
+
+    128u32.saturating_sub(bits) as u8
 }
 
 /// Compare two hex digests with a Hamming distance calculation. Returns an unsigned 8-bit integer in the range `[0,
@@ -156,15 +455,10 @@ impl Nilsimsa {
 pub fn compare(digest_a: &str, digest_b: &str) -> u8 {
     assert!(digest_a.len() == digest_b.len());
 
-    let hex_a = hex::decode(digest_a).expect("failed to decode digest A into hex");
-    let hex_b = hex::decode(digest_b).expect("failed to decode digest B into hex");
-    let mut bits = 0;
+    let digest_a: Digest = digest_a.parse().expect("failed to decode digest A into hex");
+    let digest_b: Digest = digest_b.parse().expect("failed to decode digest B into hex");
 
-    for (a, b) in hex_a.into_iter().zip(hex_b) {
-        bits += POPC[(a ^ b) as usize] as u8;
-    }
-
-    128 - bits
+    digest_a.compare(&digest_b)
 }
 
 fn tran_hash(a: u8, b: u8, c: u8, n: u8) -> u8 {
@@ -176,9 +470,88 @@ fn tran_hash(a: u8, b: u8, c: u8, n: u8) -> u8 {
 mod tests {
     extern crate test;
 
+    use std::io::Write as _;
+
     use super::*;
     use test::Bencher;
 
+    const TEST_STRING_HEX: &str = "42c82c184080082040001004000000084e1043b0c0925829003e84c860410010";
+
+    #[test]
+    fn digest_round_trips_through_string() {
+        let mut hash = Nilsimsa::new();
+        hash.update("test string");
+        let digest = hash.digest();
+
+        let parsed: Digest = digest.to_string().parse().unwrap();
+        assert_eq!(digest, parsed);
+        assert_eq!(format!("{:x}", digest), TEST_STRING_HEX);
+        assert_eq!(format!("{:X}", digest), TEST_STRING_HEX.to_uppercase());
+    }
+
+    #[test]
+    fn digest_from_str_rejects_invalid_hex() {
+        assert!(matches!(
+            "not hex".parse::<Digest>(),
+            Err(DigestParseError::InvalidHex(_))
+        ));
+    }
+
+    #[test]
+    fn digest_from_str_rejects_wrong_length() {
+        assert!(matches!(
+            "42c8".parse::<Digest>(),
+            Err(DigestParseError::InvalidLength(2))
+        ));
+    }
+
+    #[test]
+    fn update_bytes_matches_update_for_the_same_input() {
+        let mut from_str = Nilsimsa::new();
+        from_str.update("test string");
+
+        let mut from_bytes = Nilsimsa::new();
+        from_bytes.update_bytes(b"test string");
+
+        assert_eq!(from_str.digest(), from_bytes.digest());
+    }
+
+    #[test]
+    fn write_impl_matches_update() {
+        let mut from_update = Nilsimsa::new();
+        from_update.update("test string");
+
+        let mut from_write = Nilsimsa::new();
+        from_write.write_all(b"test string").unwrap();
+
+        assert_eq!(from_update.digest(), from_write.digest());
+    }
+
+    #[test]
+    fn state_round_trips_and_resumes_hashing() {
+        let mut hash = Nilsimsa::new();
+        hash.update("test ");
+
+        let state = hash.to_state();
+        let mut resumed = Nilsimsa::from_state(state).unwrap();
+        resumed.update("string");
+        hash.update("string");
+
+        assert_eq!(hash.digest(), resumed.digest());
+    }
+
+    #[test]
+    fn from_state_rejects_wrong_acc_length() {
+        let state = Nilsimsa::new().to_state();
+        let mut corrupted = state;
+        corrupted.acc.truncate(3);
+
+        assert!(matches!(
+            Nilsimsa::from_state(corrupted),
+            Err(StateError::InvalidAccLength(3))
+        ));
+    }
+
     #[bench]
     fn expected_output(b: &mut Bencher) {
         b.iter(|| {
@@ -187,7 +560,7 @@ mod tests {
             let output = hash.digest();
 
             assert_eq!(
-                output,
+                output.to_string(),
                 "42c82c184080082040001004000000084e1043b0c0925829003e84c860410010"
             );
         })
@@ -262,7 +635,7 @@ mod tests {
             let output = hash.digest();
 
             assert_eq!(
-                output,
+                output.to_string(),
                 "9b8c8a910218eb47d0f283c5ac948ba12c0ba8112513eae8291befdca3f4e066"
             );
         })